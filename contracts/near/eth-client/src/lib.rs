@@ -197,6 +197,14 @@ impl EthClient {
         }
     }
 
+    /// Returns whether proofs of events in the given block can be verified right now: the block
+    /// is in the canonical chain, has enough confirmations and was not garbage collected yet.
+    /// Advisory only, new headers may change the answer by the time a proof is submitted.
+    #[result_serializer(borsh)]
+    pub fn can_accept_proof_for_block(&self, #[serializer(borsh)] index: u64) -> bool {
+        self.block_hash_safe(index).is_some()
+    }
+
     /// Add the block header to the client.
     /// `block_header` -- RLP-encoded Ethereum header;
     /// `dag_nodes` -- dag nodes with their merkle proofs.
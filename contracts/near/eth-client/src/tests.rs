@@ -551,6 +551,41 @@ fn add_two_blocks_from_400000() {
     assert_hashes_equal_to_contract_hashes(&contract, &heights, &hashes);
 }
 
+#[test]
+fn can_accept_proof_for_block_checks_confirmations_and_gc() {
+    testing_env!(get_context(vec![], false));
+
+    let blocks: Vec<BlockWithProofs> = (10_234_001..10_234_012)
+        .map(|i| read_block(format!("./src/data/{}.json", i)))
+        .collect();
+
+    // Trusted signer mode, so headers are added without the PoW validation.
+    let mut contract = EthClient::init(
+        true,
+        0,
+        vec![],
+        blocks[0].header_rlp.0.clone(),
+        5,
+        5,
+        2,
+        Some("bob.near".to_string()),
+    );
+    for block in blocks.iter().skip(1) {
+        contract.add_block_header(block.header_rlp.0.clone(), vec![]);
+    }
+    assert_eq!(contract.last_block_number(), 10_234_011);
+
+    // Not enough confirmations yet.
+    assert!(!contract.can_accept_proof_for_block(10_234_011));
+    assert!(!contract.can_accept_proof_for_block(10_234_010));
+    // Confirmed and still in the canonical chain.
+    assert!(contract.can_accept_proof_for_block(10_234_009));
+    assert!(contract.can_accept_proof_for_block(10_234_007));
+    // Already garbage collected.
+    assert!(!contract.can_accept_proof_for_block(10_234_006));
+    assert!(!contract.can_accept_proof_for_block(10_234_001));
+}
+
 #[cfg(feature = "expensive_tests")]
 #[test]
 fn predumped_block_can_be_added() {
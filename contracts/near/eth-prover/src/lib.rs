@@ -14,11 +14,43 @@ const BLOCK_HASH_SAFE_GAS: Gas = 10_000_000_000_000;
 /// Gas to call on_block_hash
 const ON_BLOCK_HASH_GAS: Gas = 5_000_000_000_000;
 
+/// Default maximum number of nodes accepted in a receipt proof. The receipt trie is keyed by the
+/// rlp encoded receipt index, so valid proofs are far shorter. Deeper proofs only waste gas.
+const DEFAULT_MAX_PROOF_DEPTH: u64 = 32;
+
+/// Gas to decode the log entry, the receipt and the header in verify_log_entry, hash the header
+/// and check the receipt bloom. Not measured, a generous allowance.
+const VERIFY_LOG_ENTRY_BASE_GAS: Gas = 5_000_000_000_000;
+
+/// Gas to hash a single node of the receipt proof and walk its rlp items. Not measured: the
+/// hashing host calls alone take about 15 Ggas a node, the rest is an allowance for the wasm rlp
+/// decoding.
+const PROOF_NODE_GAS: Gas = 2_000_000_000_000;
+
+/// Gas per byte of the receipt proof, covering input reading, hashing and decoding. Not
+/// measured: the host calls alone take about 25 Mgas a byte.
+const PROOF_BYTE_GAS: Gas = 100_000_000;
+
+/// Gas to create the EthClient call and the on_block_hash callback, not counting the gas
+/// attached to them: two function call receipts and a data dependency between them cost about
+/// 19.1 Tgas with the runtime fee config.
+const VERIFY_LOG_ENTRY_PROMISES_GAS: Gas = 20_000_000_000_000;
+
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
 pub struct EthProver {
     bridge_smart_contract: AccountId,
     paused: Mask,
+    /// Maximum number of nodes accepted in a receipt proof by `verify_log_entry`.
+    /// Can only be changed by the contract itself, like the paused mask.
+    max_proof_depth: u64,
+}
+
+/// State layout before `max_proof_depth` was introduced. Only used by `migrate`.
+#[derive(BorshDeserialize, BorshSerialize)]
+struct EthProverV1 {
+    bridge_smart_contract: AccountId,
+    paused: Mask,
 }
 
 fn assert_self() {
@@ -48,6 +80,15 @@ fn get_vec(data: &Rlp, pos: usize) -> Vec<u8> {
     data.at(pos).unwrap().as_val::<Vec<u8>>().unwrap()
 }
 
+/// Check that the bloom may contain the address and all the topics of the log entry.
+fn bloom_contains_log_entry(bloom: &Bloom, log_entry: &LogEntry) -> bool {
+    bloom.contains_input(&(log_entry.address.0).0)
+        && log_entry
+            .topics
+            .iter()
+            .all(|topic| bloom.contains_input(&(topic.0).0))
+}
+
 const PAUSE_VERIFY: Mask = 1;
 
 #[near_bindgen]
@@ -61,9 +102,38 @@ impl EthProver {
         Self {
             bridge_smart_contract,
             paused: Mask::default(),
+            max_proof_depth: DEFAULT_MAX_PROOF_DEPTH,
         }
     }
 
+    /// Migrate the state from the layout without `max_proof_depth`, setting it to the default.
+    /// This method can only be called by the EthProver contract itself, right after deploying the
+    /// new code. It fails on already migrated state, since it no longer matches the old layout.
+    pub fn migrate() {
+        assert_self();
+        let old_state: EthProverV1 = env::state_read().expect("The contract is not initialized");
+        env::state_write(&EthProver {
+            bridge_smart_contract: old_state.bridge_smart_contract,
+            paused: old_state.paused,
+            max_proof_depth: DEFAULT_MAX_PROOF_DEPTH,
+        });
+    }
+
+    #[result_serializer(borsh)]
+    pub fn get_max_proof_depth(&self) -> u64 {
+        self.max_proof_depth
+    }
+
+    /// Update the maximum number of nodes accepted in a receipt proof. It can't be zero, since
+    /// every proof has at least one node.
+    /// This method can only be called by the EthProver contract itself.
+    #[result_serializer(borsh)]
+    pub fn set_max_proof_depth(&mut self, #[serializer(borsh)] max_proof_depth: u64) {
+        assert_self();
+        assert!(max_proof_depth > 0, "Max proof depth should be positive");
+        self.max_proof_depth = max_proof_depth;
+    }
+
     /// Implementation of the callback when the EthClient returns data.
     /// This method can only be called by the EthProver contract itself (e.g. as callback).
     /// - `block_hash` is the actual data from the EthClient call
@@ -116,6 +186,10 @@ impl EthProver {
         #[serializer(borsh)] skip_bridge_call: bool,
     ) -> PromiseOrValue<bool> {
         self.check_not_paused(PAUSE_VERIFY);
+        assert!(
+            proof.len() as u64 <= self.max_proof_depth,
+            "Proof is too deep"
+        );
         let log_entry: LogEntry = rlp::decode(log_entry_data.as_slice()).unwrap();
         let receipt: Receipt = rlp::decode(receipt_data.as_slice()).unwrap();
         let header: BlockHeader = rlp::decode(header_data.as_slice()).unwrap();
@@ -123,6 +197,12 @@ impl EthProver {
         // Verify log_entry included in receipt
         assert_eq!(receipt.logs[log_index as usize], log_entry);
 
+        // Cheap pre-filter before the trie proof and the bridge call: a receipt whose bloom
+        // can't contain the log entry is invalid.
+        if !bloom_contains_log_entry(&receipt.log_bloom, &log_entry) {
+            return PromiseOrValue::Value(false);
+        }
+
         // Verify receipt included into header
         let data =
             Self::verify_trie_proof(header.receipts_root, rlp::encode(&receipt_index), proof);
@@ -149,6 +229,33 @@ impl EthProver {
         .into()
     }
 
+    /// Loose upper bound of the gas `verify_log_entry` needs for a receipt proof with
+    /// `proof_nodes` nodes of `proof_bytes` bytes in total. Unless `skip_bridge_call` is set, it
+    /// includes creating the EthClient call and its callback and the gas attached to them.
+    /// The per-node and per-byte figures are allowances rather than measurements of the wasm
+    /// execution, so relayers may attach less once they have measured real proofs.
+    /// The estimate only depends on the proof dimensions, so relayers can size transactions
+    /// before submitting them. It doesn't check `max_proof_depth`: deeper proofs still get an
+    /// estimate, but `verify_log_entry` rejects them.
+    #[result_serializer(borsh)]
+    pub fn estimate_verify_log_entry_gas(
+        #[serializer(borsh)] proof_nodes: u64,
+        #[serializer(borsh)] proof_bytes: u64,
+        #[serializer(borsh)] skip_bridge_call: bool,
+    ) -> Gas {
+        let verify_gas = VERIFY_LOG_ENTRY_BASE_GAS
+            .saturating_add(proof_nodes.saturating_mul(PROOF_NODE_GAS))
+            .saturating_add(proof_bytes.saturating_mul(PROOF_BYTE_GAS));
+        if skip_bridge_call {
+            verify_gas
+        } else {
+            verify_gas
+                .saturating_add(VERIFY_LOG_ENTRY_PROMISES_GAS)
+                .saturating_add(BLOCK_HASH_SAFE_GAS)
+                .saturating_add(ON_BLOCK_HASH_GAS)
+        }
+    }
+
     /// Verify the proof recursively traversing through the key.
     /// Return the value at the end of the key, in case the proof is valid.
     ///
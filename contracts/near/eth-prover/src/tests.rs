@@ -1,8 +1,9 @@
 #[cfg(test)]
 mod tests {
-    use crate::EthProver;
+    use crate::{bloom_contains_log_entry, EthProver, EthProverV1, DEFAULT_MAX_PROOF_DEPTH};
+    use eth_types::*;
     use hex::{FromHex, ToHex};
-    use near_sdk::PromiseOrValue;
+    use near_sdk::{env, PromiseOrValue};
     use rlp::RlpStream;
     use serde::{Deserialize, Deserializer};
 
@@ -53,12 +54,39 @@ mod tests {
         }
     }
 
-    #[test]
-    fn simple_tx_res() {
-        testing_env!(get_context(vec![], false));
+    fn get_self_context() -> VMContext {
+        VMContext {
+            predecessor_account_id: "alice.near".to_string(),
+            ..get_context(vec![], false)
+        }
+    }
 
-        let contract = EthProver::init("ethbridge".to_string());
+    struct VerifyLogEntryArgs {
+        log_index: u64,
+        log_entry: Vec<u8>,
+        receipt_index: u64,
+        receipt_data: Vec<u8>,
+        header_data: Vec<u8>,
+        proof: Vec<Vec<u8>>,
+    }
 
+    fn verify(
+        contract: &EthProver,
+        args: VerifyLogEntryArgs,
+        skip_bridge_call: bool,
+    ) -> PromiseOrValue<bool> {
+        contract.verify_log_entry(
+            args.log_index,
+            args.log_entry,
+            args.receipt_index,
+            args.receipt_data,
+            args.header_data,
+            args.proof,
+            skip_bridge_call,
+        )
+    }
+
+    fn simple_tx_args() -> VerifyLogEntryArgs {
         // Following data could be extracted by:
         // NODE_URL="https://mainnet.infura.io/v3/b5f870422ee5454fb11937e947154cd2" TX_HASH="0xb540248a9cca048c5861dec953d7a776bc1944319b9bd27a462469c8a437f4ff" EVENT_INDEX=0 node extract.js
 
@@ -81,26 +109,17 @@ mod tests {
         stream.out()
     }).collect();
 
-        if let PromiseOrValue::Value(true) = contract.verify_log_entry(
+        VerifyLogEntryArgs {
             log_index,
             log_entry,
             receipt_index,
             receipt_data,
             header_data,
             proof,
-            true,
-        ) {
-        } else {
-            panic!();
         }
     }
 
-    #[test]
-    fn complex_tx_res() {
-        testing_env!(get_context(vec![], false));
-
-        let contract = EthProver::init("ethbridge".to_string());
-
+    fn complex_tx_args() -> VerifyLogEntryArgs {
         // Following data could be extracted by:
         // NODE_URL="https://mainnet.infura.io/v3/b5f870422ee5454fb11937e947154cd2" TX_HASH="0xa7e1633e8099ea2b72496207b76a0e04a761c52f48c82bfcf6b327495258e4e0" EVENT_INDEX=0 node extract.js
 
@@ -199,20 +218,204 @@ mod tests {
         stream.out()
     }).collect();
 
-        if let PromiseOrValue::Value(true) = contract.verify_log_entry(
+        VerifyLogEntryArgs {
             log_index,
             log_entry,
             receipt_index,
             receipt_data,
             header_data,
             proof,
+        }
+    }
+
+    #[test]
+    fn simple_tx_res() {
+        testing_env!(get_context(vec![], false));
+
+        let contract = EthProver::init("ethbridge".to_string());
+
+        if let PromiseOrValue::Value(true) = verify(&contract, simple_tx_args(), true) {
+        } else {
+            panic!();
+        }
+    }
+
+    #[test]
+    fn complex_tx_res() {
+        testing_env!(get_context(vec![], false));
+
+        let contract = EthProver::init("ethbridge".to_string());
+
+        if let PromiseOrValue::Value(true) = verify(&contract, complex_tx_args(), true) {
+        } else {
+            panic!();
+        }
+    }
+
+    #[test]
+    fn receipt_bloom_contains_log_entry() {
+        testing_env!(get_context(vec![], false));
+
+        let args = simple_tx_args();
+        let receipt: Receipt = rlp::decode(args.receipt_data.as_slice()).unwrap();
+        let mut log_entry: LogEntry = rlp::decode(args.log_entry.as_slice()).unwrap();
+        assert!(bloom_contains_log_entry(&receipt.log_bloom, &log_entry));
+
+        log_entry.topics.push(H256::from([0x11u8; 32]));
+        assert!(!bloom_contains_log_entry(&receipt.log_bloom, &log_entry));
+    }
+
+    #[test]
+    fn log_entry_missing_from_bloom_rejected_early() {
+        testing_env!(get_context(vec![], false));
+
+        let contract = EthProver::init("ethbridge".to_string());
+
+        let mut args = simple_tx_args();
+        let mut receipt: Receipt = rlp::decode(args.receipt_data.as_slice()).unwrap();
+        receipt.log_bloom = Bloom::default();
+        args.receipt_data = rlp::encode(&receipt);
+        // An empty proof would panic in the trie verification, so `false` here means the
+        // receipt was rejected before reaching it.
+        args.proof = vec![];
+
+        if let PromiseOrValue::Value(false) = verify(&contract, args, true) {
+        } else {
+            panic!();
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Proof is too deep")]
+    fn too_deep_proof_rejected() {
+        testing_env!(get_context(vec![], false));
+
+        let contract = EthProver::init("ethbridge".to_string());
+        contract.verify_log_entry(
+            0,
+            vec![],
+            0,
+            vec![],
+            vec![],
+            vec![vec![]; DEFAULT_MAX_PROOF_DEPTH as usize + 1],
             true,
-        ) {
+        );
+    }
+
+    #[test]
+    fn proof_at_max_depth_accepted() {
+        testing_env!(get_self_context());
+
+        let mut contract = EthProver::init("ethbridge".to_string());
+        let args = simple_tx_args();
+        contract.set_max_proof_depth(args.proof.len() as u64);
+
+        if let PromiseOrValue::Value(true) = verify(&contract, args, true) {
         } else {
             panic!();
         }
     }
 
+    #[test]
+    #[should_panic(expected = "Proof is too deep")]
+    fn proof_over_configured_depth_rejected() {
+        testing_env!(get_self_context());
+
+        let mut contract = EthProver::init("ethbridge".to_string());
+        let args = complex_tx_args();
+        contract.set_max_proof_depth(args.proof.len() as u64 - 1);
+        verify(&contract, args, true);
+    }
+
+    #[test]
+    #[should_panic]
+    fn set_max_proof_depth_only_by_self() {
+        testing_env!(get_context(vec![], false));
+
+        let mut contract = EthProver::init("ethbridge".to_string());
+        contract.set_max_proof_depth(100);
+    }
+
+    #[test]
+    #[should_panic(expected = "Max proof depth should be positive")]
+    fn zero_max_proof_depth_rejected() {
+        testing_env!(get_self_context());
+
+        let mut contract = EthProver::init("ethbridge".to_string());
+        contract.set_max_proof_depth(0);
+    }
+
+    #[test]
+    fn migrate_sets_default_max_proof_depth() {
+        testing_env!(get_self_context());
+
+        env::state_write(&EthProverV1 {
+            bridge_smart_contract: "ethbridge".to_string(),
+            paused: 1,
+        });
+        EthProver::migrate();
+
+        let contract: EthProver = env::state_read().unwrap();
+        assert_eq!(contract.bridge_smart_contract, "ethbridge");
+        assert_eq!(contract.paused, 1);
+        assert_eq!(contract.get_max_proof_depth(), DEFAULT_MAX_PROOF_DEPTH);
+    }
+
+    #[test]
+    fn gas_estimate_grows_with_proof_size() {
+        let small = EthProver::estimate_verify_log_entry_gas(1, 500, false);
+        let more_nodes = EthProver::estimate_verify_log_entry_gas(5, 500, false);
+        let more_bytes = EthProver::estimate_verify_log_entry_gas(1, 5000, false);
+        assert!(small < more_nodes);
+        assert!(small < more_bytes);
+        assert!(more_nodes < EthProver::estimate_verify_log_entry_gas(5, 5000, false));
+    }
+
+    #[test]
+    fn gas_estimate_without_bridge_call_is_lower() {
+        assert!(
+            EthProver::estimate_verify_log_entry_gas(3, 1500, true)
+                < EthProver::estimate_verify_log_entry_gas(3, 1500, false)
+        );
+    }
+
+    #[test]
+    fn gas_estimate_covers_metered_gas() {
+        testing_env!(get_context(vec![], false));
+
+        // The mocked blockchain only meters host calls and promises, not the wasm execution, so
+        // this checks the estimate doesn't leave out those costs, not that it is tight.
+        let contract = EthProver::init("ethbridge".to_string());
+        for args in vec![simple_tx_args(), complex_tx_args()] {
+            let proof_nodes = args.proof.len() as u64;
+            let proof_bytes: u64 = args.proof.iter().map(|node| node.len() as u64).sum();
+
+            let used_gas_before = env::used_gas();
+            if let PromiseOrValue::Promise(_) = verify(&contract, args, false) {
+            } else {
+                panic!();
+            }
+            let used_gas = env::used_gas() - used_gas_before;
+
+            assert!(
+                EthProver::estimate_verify_log_entry_gas(proof_nodes, proof_bytes, false)
+                    >= used_gas
+            );
+        }
+    }
+
+    #[test]
+    fn gas_estimate_saturates() {
+        assert_eq!(
+            EthProver::estimate_verify_log_entry_gas(u64::MAX, 500, false),
+            u64::MAX
+        );
+        assert_eq!(
+            EthProver::estimate_verify_log_entry_gas(3, u64::MAX, true),
+            u64::MAX
+        );
+    }
+
     #[test]
     fn complex_test2() {
         let log_index = 0;
@@ -101,6 +101,19 @@ arr_declare_wrapper_and_serde!(H512, 64);
 arr_declare_wrapper_and_serde!(H520, 65);
 arr_declare_wrapper_and_serde!(Bloom, 256);
 
+impl Bloom {
+    /// Returns whether the bloom may contain the given raw input (an address or a topic).
+    /// False positives are possible, false negatives are not.
+    pub fn contains_input(&self, input: &[u8]) -> bool {
+        let hash = near_keccak256(input);
+        let bloom = &(self.0).0;
+        (0..3).all(|i| {
+            let bit = (((hash[2 * i] as usize) << 8) | hash[2 * i + 1] as usize) & 2047;
+            bloom[255 - bit / 8] & (1 << (bit % 8)) != 0
+        })
+    }
+}
+
 macro_rules! uint_declare_wrapper_and_serde {
     ($name: ident, $len: expr) => {
         #[derive(